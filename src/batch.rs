@@ -0,0 +1,196 @@
+// Batch verification: instead of checking n signatures one at a time,
+// take a random linear combination of all n per-signature relations and
+// check the single aggregated equation with one multi-scalar multiply.
+
+use hi_crypto::bellman::pairing::ff::{Field, PrimeField};
+use hi_crypto::blake2b_simd::Params as Blake2bParams;
+use hi_crypto::jubjub::{edwards, FixedGenerators, JubjubEngine, JubjubParams, Unknown};
+use rand::Rng;
+
+// data_to_be_signed is vk_bar || msg, same as PrivateKey::sign/PublicKey::verify.
+struct BatchEntry<E: JubjubEngine> {
+    r: edwards::Point<E, Unknown>,
+    s: E::Fs,
+    vk: edwards::Point<E, Unknown>,
+    data_to_be_signed: Vec<u8>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BatchVerificationError;
+
+pub struct BatchVerifier<'a, E: JubjubEngine> {
+    p_g: FixedGenerators,
+    params: &'a E::Params,
+    entries: Vec<BatchEntry<E>>,
+}
+
+impl<'a, E: JubjubEngine> BatchVerifier<'a, E> {
+    pub fn new(p_g: FixedGenerators, params: &'a E::Params) -> Self {
+        BatchVerifier {
+            p_g,
+            params,
+            entries: vec![],
+        }
+    }
+
+    pub fn queue(
+        &mut self,
+        r: edwards::Point<E, Unknown>,
+        s: E::Fs,
+        vk: edwards::Point<E, Unknown>,
+        data_to_be_signed: Vec<u8>,
+    ) {
+        self.entries.push(BatchEntry {
+            r,
+            s,
+            vk,
+            data_to_be_signed,
+        });
+    }
+
+    // Checks [8]([Sum z_i*S_i]*B - Sum [z_i]*R_i - Sum [z_i*c_i]*A_i) == O
+    // for independent random z_i.
+    pub fn verify<R: Rng>(self, rng: &mut R) -> Result<(), BatchVerificationError> {
+        if self.entries.is_empty() {
+            return Ok(());
+        }
+
+        for entry in &self.entries {
+            if is_small_order(&entry.r, self.params) || is_small_order(&entry.vk, self.params) {
+                return Err(BatchVerificationError);
+            }
+        }
+
+        let b = edwards::Point::<E, Unknown>::from(self.params.generator(self.p_g).clone());
+
+        let mut s_acc = E::Fs::zero();
+        let mut acc = edwards::Point::<E, Unknown>::zero();
+
+        for entry in &self.entries {
+            let z_i = random_128_bit_scalar::<E, R>(rng);
+
+            let mut r_bar = vec![];
+            entry.r.write(&mut r_bar).expect("point serialization cannot fail");
+            let c_i = h_star::<E>(&r_bar, &entry.data_to_be_signed);
+
+            let mut z_s = entry.s;
+            z_s.mul_assign(&z_i);
+            s_acc.add_assign(&z_s);
+
+            let neg_r = entry.r.negate();
+            acc = acc.add(&neg_r.mul(z_i.into_repr(), self.params), self.params);
+
+            let mut z_c = c_i;
+            z_c.mul_assign(&z_i);
+            let neg_vk = entry.vk.negate();
+            acc = acc.add(&neg_vk.mul(z_c.into_repr(), self.params), self.params);
+        }
+
+        acc = acc.add(&b.mul(s_acc.into_repr(), self.params), self.params);
+
+        // clear the cofactor, same as the circuit's triple-doubling
+        acc = acc.double(self.params);
+        acc = acc.double(self.params);
+        acc = acc.double(self.params);
+
+        if is_identity(&acc) {
+            Ok(())
+        } else {
+            Err(BatchVerificationError)
+        }
+    }
+}
+
+fn random_128_bit_scalar<E: JubjubEngine, R: Rng>(rng: &mut R) -> E::Fs {
+    let value: u128 = rng.gen();
+    let mut repr = <E::Fs as PrimeField>::Repr::default();
+    let limbs = repr.as_mut();
+    limbs[0] = value as u64;
+    limbs[1] = (value >> 64) as u64;
+    E::Fs::from_repr(repr).expect("128-bit values are valid field elements")
+}
+
+fn is_identity<E: JubjubEngine, Subgroup>(p: &edwards::Point<E, Subgroup>) -> bool {
+    let (x, y) = p.into_xy();
+    x.is_zero() && y == E::Fr::one()
+}
+
+fn is_small_order<E: JubjubEngine>(p: &edwards::Point<E, Unknown>, params: &E::Params) -> bool {
+    is_identity(&p.mul_by_cofactor(params))
+}
+
+// Same Blake2b challenge hash the in-circuit signature equation derives
+// via circuit::blake2b::blake2b, via hi_crypto's re-export of blake2b_simd.
+pub(crate) fn h_star<E: JubjubEngine>(a: &[u8], b: &[u8]) -> E::Fs {
+    let hash = Blake2bParams::new()
+        .hash_length(64)
+        .personal(b"Zcash_RedJubjubH")
+        .to_state()
+        .update(a)
+        .update(b)
+        .finalize();
+    E::Fs::to_uniform(hash.as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hi_crypto::bellman::pairing::bls12_381::Bls12;
+    use hi_crypto::jubjub::JubjubBls12;
+    use hi_crypto::redjubjub::{PrivateKey, PublicKey};
+    use rand::thread_rng;
+
+    fn signed_entry(
+        p_g: FixedGenerators,
+        params: &<Bls12 as JubjubEngine>::Params,
+        rng: &mut impl Rng,
+        msg: &[u8],
+    ) -> (edwards::Point<Bls12, Unknown>, <Bls12 as JubjubEngine>::Fs, edwards::Point<Bls12, Unknown>, Vec<u8>) {
+        let sk = PrivateKey::<Bls12>(rng.gen());
+        let vk = PublicKey::from_private(&sk, p_g, params);
+
+        let mut data_to_be_signed = vec![];
+        vk.write(&mut data_to_be_signed).unwrap();
+        data_to_be_signed.extend_from_slice(msg);
+
+        let sig = sk.sign(&data_to_be_signed, rng, p_g, params);
+        assert!(vk.verify(&data_to_be_signed, &sig, p_g, params));
+
+        let mut sig_bytes = [0u8; 64];
+        sig.write(&mut sig_bytes[..]).unwrap();
+        let r = PublicKey::<Bls12>::read(&sig_bytes[..32], params).unwrap().0;
+        let s = PrivateKey::<Bls12>::read(&sig_bytes[32..]).unwrap().0;
+
+        (r, s, vk.0, data_to_be_signed)
+    }
+
+    #[test]
+    fn accepts_a_valid_batch_and_rejects_a_tampered_one() {
+        let params = &JubjubBls12::new();
+        let rng = &mut thread_rng();
+        let p_g = FixedGenerators::SpendingKeyGenerator;
+
+        let entries: Vec<_> = (0..4)
+            .map(|i| signed_entry(p_g, params, rng, format!("batch message {}", i).as_bytes()))
+            .collect();
+
+        let mut verifier = BatchVerifier::<Bls12>::new(p_g, params);
+        for (r, s, vk, data_to_be_signed) in entries.iter().cloned() {
+            verifier.queue(r, s, vk, data_to_be_signed);
+        }
+        assert!(verifier.verify(rng).is_ok());
+
+        let mut tampered = BatchVerifier::<Bls12>::new(p_g, params);
+        for (i, (r, s, vk, data_to_be_signed)) in entries.into_iter().enumerate() {
+            let s = if i == 0 {
+                let mut bumped = s;
+                bumped.add_assign(&<Bls12 as JubjubEngine>::Fs::one());
+                bumped
+            } else {
+                s
+            };
+            tampered.queue(r, s, vk, data_to_be_signed);
+        }
+        assert_eq!(tampered.verify(rng), Err(BatchVerificationError));
+    }
+}
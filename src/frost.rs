@@ -0,0 +1,303 @@
+// Two-round FROST threshold signing for RedJubjub: a t-of-n group jointly
+// produces a single (R, S) over the re-randomized key rk = group_public +
+// [alpha]*B, verifiable the same way as a single-party signature over rk.
+
+use crate::batch::h_star;
+use hi_crypto::bellman::pairing::ff::{Field, PrimeField};
+use hi_crypto::jubjub::{edwards, FixedGenerators, JubjubEngine, JubjubParams, Unknown};
+use rand::Rng;
+
+pub struct KeyPackage<E: JubjubEngine> {
+    pub identifier: u32,
+    pub signing_share: E::Fs,
+    pub group_public: edwards::Point<E, Unknown>,
+}
+
+// Splits secret into n Shamir shares of a degree-(t-1) polynomial, any t
+// of which reconstruct a signature under group_public = [secret]*B.
+pub fn trusted_dealer_keygen<E: JubjubEngine, R: Rng>(
+    secret: E::Fs,
+    t: usize,
+    n: usize,
+    p_g: FixedGenerators,
+    params: &E::Params,
+    rng: &mut R,
+) -> (Vec<KeyPackage<E>>, edwards::Point<E, Unknown>) {
+    let mut coeffs = Vec::with_capacity(t);
+    coeffs.push(secret);
+    for _ in 1..t {
+        coeffs.push(rng.gen());
+    }
+
+    let b = edwards::Point::<E, Unknown>::from(params.generator(p_g).clone());
+    let group_public = b.mul(secret.into_repr(), params);
+
+    let shares = (1..=n)
+        .map(|i| KeyPackage {
+            identifier: i as u32,
+            signing_share: poly_eval::<E>(&coeffs, scalar_from_u32::<E>(i as u32)),
+            group_public: group_public.clone(),
+        })
+        .collect();
+
+    (shares, group_public)
+}
+
+// rk = group_public + [alpha]*B, same derivation as derive_rk's rk = ak + [alpha]*B.
+pub fn randomize_group_public<E: JubjubEngine>(
+    group_public: &edwards::Point<E, Unknown>,
+    alpha: E::Fs,
+    p_g: FixedGenerators,
+    params: &E::Params,
+) -> edwards::Point<E, Unknown> {
+    let b = edwards::Point::<E, Unknown>::from(params.generator(p_g).clone());
+    group_public.add(&b.mul(alpha.into_repr(), params), params)
+}
+
+// Round one: nonces (d_i, e_i) kept secret, commitments (D_i, E_i) = ([d_i]*B, [e_i]*B) published.
+pub struct SigningNonces<E: JubjubEngine> {
+    hiding: E::Fs,
+    binding: E::Fs,
+}
+
+#[derive(Clone)]
+pub struct SigningCommitment<E: JubjubEngine> {
+    pub identifier: u32,
+    pub hiding: edwards::Point<E, Unknown>,
+    pub binding: edwards::Point<E, Unknown>,
+}
+
+pub fn round_one<E: JubjubEngine, R: Rng>(
+    identifier: u32,
+    p_g: FixedGenerators,
+    params: &E::Params,
+    rng: &mut R,
+) -> (SigningNonces<E>, SigningCommitment<E>) {
+    let d: E::Fs = rng.gen();
+    let e: E::Fs = rng.gen();
+    let b = edwards::Point::<E, Unknown>::from(params.generator(p_g).clone());
+
+    let nonces = SigningNonces { hiding: d, binding: e };
+    let commitment = SigningCommitment {
+        identifier,
+        hiding: b.mul(d.into_repr(), params),
+        binding: b.mul(e.into_repr(), params),
+    };
+    (nonces, commitment)
+}
+
+pub struct SignatureShare<E: JubjubEngine> {
+    pub identifier: u32,
+    pub z: E::Fs,
+}
+
+// Round two: z_i = d_i + e_i*rho_i + lambda_i*s_i*c, where c is the
+// challenge over rk, not the raw group_public. Every signer must get the
+// same rk (same alpha) or the shares won't combine.
+pub fn round_two<E: JubjubEngine>(
+    key_package: &KeyPackage<E>,
+    nonces: &SigningNonces<E>,
+    commitments: &[SigningCommitment<E>],
+    rk: &edwards::Point<E, Unknown>,
+    msg: &[u8],
+    params: &E::Params,
+) -> SignatureShare<E> {
+    let data_to_be_signed = data_to_be_signed::<E>(rk, msg);
+    let group_commitment = group_commitment::<E>(commitments, &data_to_be_signed, params);
+
+    let mut r_bar = vec![];
+    group_commitment
+        .write(&mut r_bar)
+        .expect("point serialization cannot fail");
+    let c = h_star::<E>(&r_bar, &data_to_be_signed);
+
+    let all_ids: Vec<u32> = commitments.iter().map(|commitment| commitment.identifier).collect();
+    let lambda_i = lagrange_coefficient::<E>(key_package.identifier, &all_ids);
+    let rho_i = binding_factor::<E>(key_package.identifier, &data_to_be_signed, commitments);
+
+    let mut z = nonces.hiding;
+    let mut e_rho = nonces.binding;
+    e_rho.mul_assign(&rho_i);
+    z.add_assign(&e_rho);
+
+    let mut lambda_s_c = lambda_i;
+    lambda_s_c.mul_assign(&key_package.signing_share);
+    lambda_s_c.mul_assign(&c);
+    z.add_assign(&lambda_s_c);
+
+    SignatureShare { identifier: key_package.identifier, z }
+}
+
+// Sums every share and folds in alpha*c, turning Sum z_i = r + c*s into
+// the rk-bound S = r + c*(s + alpha) = r + c*rsk.
+pub fn aggregate<E: JubjubEngine>(
+    commitments: &[SigningCommitment<E>],
+    shares: &[SignatureShare<E>],
+    rk: &edwards::Point<E, Unknown>,
+    msg: &[u8],
+    alpha: E::Fs,
+    params: &E::Params,
+) -> (edwards::Point<E, Unknown>, E::Fs) {
+    let data_to_be_signed = data_to_be_signed::<E>(rk, msg);
+    let r = group_commitment::<E>(commitments, &data_to_be_signed, params);
+
+    let mut r_bar = vec![];
+    r.write(&mut r_bar).expect("point serialization cannot fail");
+    let c = h_star::<E>(&r_bar, &data_to_be_signed);
+
+    let mut s = E::Fs::zero();
+    for share in shares {
+        s.add_assign(&share.z);
+    }
+
+    let mut alpha_c = alpha;
+    alpha_c.mul_assign(&c);
+    s.add_assign(&alpha_c);
+
+    (r, s)
+}
+
+// rk_bar || msg, same as PrivateKey::sign/PublicKey::verify.
+fn data_to_be_signed<E: JubjubEngine>(rk: &edwards::Point<E, Unknown>, msg: &[u8]) -> Vec<u8> {
+    let mut bytes = vec![];
+    rk.write(&mut bytes).expect("point serialization cannot fail");
+    bytes.extend_from_slice(msg);
+    bytes
+}
+
+fn group_commitment<E: JubjubEngine>(
+    commitments: &[SigningCommitment<E>],
+    data_to_be_signed: &[u8],
+    params: &E::Params,
+) -> edwards::Point<E, Unknown> {
+    let mut acc = edwards::Point::<E, Unknown>::zero();
+    for commitment in commitments {
+        let rho = binding_factor::<E>(commitment.identifier, data_to_be_signed, commitments);
+        let term = commitment
+            .hiding
+            .add(&commitment.binding.mul(rho.into_repr(), params), params);
+        acc = acc.add(&term, params);
+    }
+    acc
+}
+
+// rho_i = H(i, msg, {D_j, E_j})
+fn binding_factor<E: JubjubEngine>(
+    identifier: u32,
+    data_to_be_signed: &[u8],
+    commitments: &[SigningCommitment<E>],
+) -> E::Fs {
+    let mut preimage = vec![];
+    preimage.extend_from_slice(&identifier.to_le_bytes());
+    preimage.extend_from_slice(data_to_be_signed);
+    for commitment in commitments {
+        commitment.hiding.write(&mut preimage).expect("point serialization cannot fail");
+        commitment.binding.write(&mut preimage).expect("point serialization cannot fail");
+    }
+    h_star::<E>(b"Zcash_FROST_Rho", &preimage)
+}
+
+// lambda_i for reconstructing a degree-(t-1) polynomial at x = 0 from all_ids.
+fn lagrange_coefficient<E: JubjubEngine>(identifier: u32, all_ids: &[u32]) -> E::Fs {
+    let xi = scalar_from_u32::<E>(identifier);
+    let mut num = E::Fs::one();
+    let mut den = E::Fs::one();
+    for &j in all_ids {
+        if j == identifier {
+            continue;
+        }
+        let xj = scalar_from_u32::<E>(j);
+
+        let mut neg_xj = xj;
+        neg_xj.negate();
+        num.mul_assign(&neg_xj);
+
+        let mut diff = xi;
+        diff.sub_assign(&xj);
+        den.mul_assign(&diff);
+    }
+    num.mul_assign(&den.inverse().expect("signing identifiers are distinct"));
+    num
+}
+
+fn poly_eval<E: JubjubEngine>(coeffs: &[E::Fs], x: E::Fs) -> E::Fs {
+    let mut result = E::Fs::zero();
+    let mut x_pow = E::Fs::one();
+    for c in coeffs {
+        let mut term = *c;
+        term.mul_assign(&x_pow);
+        result.add_assign(&term);
+        x_pow.mul_assign(&x);
+    }
+    result
+}
+
+fn scalar_from_u32<E: JubjubEngine>(value: u32) -> E::Fs {
+    let mut repr = <E::Fs as PrimeField>::Repr::default();
+    repr.as_mut()[0] = value as u64;
+    E::Fs::from_repr(repr).expect("small integers are valid field elements")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hi_crypto::bellman::pairing::bls12_381::Bls12;
+    use hi_crypto::jubjub::JubjubBls12;
+    use hi_crypto::redjubjub::{PrivateKey, PublicKey, Signature};
+    use rand::thread_rng;
+
+    #[test]
+    fn two_of_three_aggregate_verifies_against_the_randomized_key_and_rejects_a_wrong_alpha() {
+        let params = &JubjubBls12::new();
+        let rng = &mut thread_rng();
+        let p_g = FixedGenerators::SpendingKeyGenerator;
+
+        let secret: <Bls12 as JubjubEngine>::Fs = rng.gen();
+        let (shares, group_public) = trusted_dealer_keygen::<Bls12, _>(secret, 2, 3, p_g, params, rng);
+
+        let alpha: <Bls12 as JubjubEngine>::Fs = rng.gen();
+        let rk = randomize_group_public::<Bls12>(&group_public, alpha, p_g, params);
+
+        let msg = b"two-of-three FROST spend";
+        let signers = [&shares[0], &shares[1]];
+
+        let mut nonces = vec![];
+        let mut commitments = vec![];
+        for key_package in &signers {
+            let (n, c) = round_one::<Bls12, _>(key_package.identifier, p_g, params, rng);
+            nonces.push(n);
+            commitments.push(c);
+        }
+
+        let shares: Vec<_> = signers
+            .iter()
+            .zip(nonces.iter())
+            .map(|(key_package, nonce)| round_two::<Bls12>(key_package, nonce, &commitments, &rk, msg, params))
+            .collect();
+
+        let (r, s) = aggregate::<Bls12>(&commitments, &shares, &rk, msg, alpha, params);
+
+        let mut sig_bytes = [0u8; 64];
+        r.write(&mut sig_bytes[0..32]).unwrap();
+        PrivateKey(s).write(&mut sig_bytes[32..64]).unwrap();
+        let sig = Signature::read(&sig_bytes[..]).unwrap();
+
+        let mut data_to_be_signed = vec![];
+        rk.write(&mut data_to_be_signed).unwrap();
+        data_to_be_signed.extend_from_slice(msg);
+
+        assert!(PublicKey(rk).verify(&data_to_be_signed, &sig, p_g, params));
+
+        // wrong alpha -> correction doesn't match rk -> must not verify
+        let mut wrong_alpha = alpha;
+        wrong_alpha.add_assign(&<Bls12 as JubjubEngine>::Fs::one());
+        let (wrong_r, wrong_s) = aggregate::<Bls12>(&commitments, &shares, &rk, msg, wrong_alpha, params);
+
+        let mut wrong_sig_bytes = [0u8; 64];
+        wrong_r.write(&mut wrong_sig_bytes[0..32]).unwrap();
+        PrivateKey(wrong_s).write(&mut wrong_sig_bytes[32..64]).unwrap();
+        let wrong_sig = Signature::read(&wrong_sig_bytes[..]).unwrap();
+
+        assert!(!PublicKey(rk).verify(&data_to_be_signed, &wrong_sig, p_g, params));
+    }
+}
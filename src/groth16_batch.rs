@@ -0,0 +1,140 @@
+// Batched Groth16 verification: combine every proof's three pairing terms
+// into a single multi-pairing (scaled by an independent random scalar per
+// proof) so the whole batch pays for one final exponentiation, not one
+// per proof.
+//
+// PreparedVerifyingKey's fields are private, so we take the unprepared
+// VerifyingKey instead: gamma_g2/delta_g2/alpha_g1_beta_g2 are re-derived
+// here the way prepare_verifying_key does, and the ic accumulation the
+// way verify_proof does.
+
+use hi_crypto::bellman::groth16::{Proof, VerifyingKey};
+use hi_crypto::bellman::pairing::{CurveAffine, CurveProjective, Engine};
+use hi_crypto::bellman::pairing::ff::{Field, PrimeField};
+use rand::Rng;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProofBatchVerificationError;
+
+pub fn verify_proofs_batch<E: Engine, R: Rng>(
+    vk: &VerifyingKey<E>,
+    proofs_and_inputs: &[(Proof<E>, Vec<E::Fr>)],
+    rng: &mut R,
+) -> Result<(), ProofBatchVerificationError> {
+    if proofs_and_inputs.is_empty() {
+        return Ok(());
+    }
+
+    let mut neg_gamma_g2 = vk.gamma_g2;
+    neg_gamma_g2.negate();
+    let neg_gamma_g2 = neg_gamma_g2.prepare();
+
+    let mut neg_delta_g2 = vk.delta_g2;
+    neg_delta_g2.negate();
+    let neg_delta_g2 = neg_delta_g2.prepare();
+
+    let alpha_g1_beta_g2 = E::pairing(vk.alpha_g1, vk.beta_g2);
+
+    let mut terms = Vec::with_capacity(proofs_and_inputs.len() * 3);
+    let mut z_sum = E::Fr::zero();
+
+    for (proof, public_inputs) in proofs_and_inputs {
+        if (public_inputs.len() + 1) != vk.ic.len() {
+            return Err(ProofBatchVerificationError);
+        }
+
+        let z: E::Fr = rng.gen();
+        z_sum.add_assign(&z);
+
+        let mut acc = vk.ic[0].into_projective();
+        for (input, base) in public_inputs.iter().zip(vk.ic.iter().skip(1)) {
+            acc.add_assign(&base.mul(input.into_repr()));
+        }
+
+        let scaled_a = proof.a.into_projective().mul(z.into_repr()).into_affine();
+        let scaled_acc = acc.mul(z.into_repr()).into_affine();
+        let scaled_c = proof.c.into_projective().mul(z.into_repr()).into_affine();
+
+        terms.push((scaled_a.prepare(), proof.b.prepare()));
+        terms.push((scaled_acc.prepare(), neg_gamma_g2.clone()));
+        terms.push((scaled_c.prepare(), neg_delta_g2.clone()));
+    }
+
+    let lhs = E::final_exponentiation(&E::miller_loop(
+        terms.iter().map(|(a, b)| (a, b)),
+    )).ok_or(ProofBatchVerificationError)?;
+
+    let rhs = alpha_g1_beta_g2.pow(z_sum.into_repr());
+
+    if lhs == rhs {
+        Ok(())
+    } else {
+        Err(ProofBatchVerificationError)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hi_crypto::bellman::groth16::{create_random_proof, generate_random_parameters};
+    use hi_crypto::bellman::pairing::bls12_381::Bls12;
+    use hi_crypto::bellman::{Circuit, ConstraintSystem, SynthesisError};
+    use rand::thread_rng;
+
+    // a * b = c
+    struct MultiplyDemo<E: Engine> {
+        a: Option<E::Fr>,
+        b: Option<E::Fr>,
+        c: Option<E::Fr>,
+    }
+
+    impl<E: Engine> Circuit<E> for MultiplyDemo<E> {
+        fn synthesize<CS: ConstraintSystem<E>>(self, cs: &mut CS) -> Result<(), SynthesisError> {
+            let a = cs.alloc(|| "a", || self.a.ok_or(SynthesisError::AssignmentMissing))?;
+            let b = cs.alloc(|| "b", || self.b.ok_or(SynthesisError::AssignmentMissing))?;
+            let c = cs.alloc_input(|| "c", || self.c.ok_or(SynthesisError::AssignmentMissing))?;
+
+            cs.enforce(|| "a*b=c", |lc| lc + a, |lc| lc + b, |lc| lc + c);
+
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn accepts_a_valid_batch_and_rejects_a_tampered_one() {
+        let rng = &mut thread_rng();
+
+        let params = generate_random_parameters::<Bls12, _, _>(
+            MultiplyDemo::<Bls12> { a: None, b: None, c: None },
+            rng,
+        )
+        .unwrap();
+
+        let proofs_and_inputs: Vec<_> = (2u64..6)
+            .map(|a| {
+                let a = <Bls12 as Engine>::Fr::from_str(&a.to_string()).unwrap();
+                let b = <Bls12 as Engine>::Fr::from_str("7").unwrap();
+                let mut c = a;
+                c.mul_assign(&b);
+
+                let proof = create_random_proof(
+                    MultiplyDemo::<Bls12> { a: Some(a), b: Some(b), c: Some(c) },
+                    &params,
+                    rng,
+                )
+                .unwrap();
+
+                (proof, vec![c])
+            })
+            .collect();
+
+        assert!(verify_proofs_batch(&params.vk, &proofs_and_inputs, rng).is_ok());
+
+        let mut tampered = proofs_and_inputs;
+        tampered[0].1[0].add_assign(&<Bls12 as Engine>::Fr::one());
+        assert_eq!(
+            verify_proofs_batch(&params.vk, &tampered, rng),
+            Err(ProofBatchVerificationError),
+        );
+    }
+}
@@ -0,0 +1,5 @@
+mod spend_authority_signature;
+pub mod batch;
+pub mod frost;
+pub mod spend;
+pub mod groth16_batch;
@@ -0,0 +1,231 @@
+// The full Sapling spend circuit: value commitment, note commitment,
+// nullifier, and Merkle authentication path up to a public anchor.
+
+use hi_crypto::bellman::{Circuit, ConstraintSystem, SynthesisError};
+use hi_crypto::circuit::blake2s::blake2s;
+use hi_crypto::circuit::boolean::{u64_into_boolean_vec_le, AllocatedBit, Boolean, field_into_boolean_vec_le};
+use hi_crypto::circuit::ecc::EdwardsPoint;
+use hi_crypto::circuit::num::AllocatedNum;
+use hi_crypto::circuit::pedersen_hash::{pedersen_hash, Personalization};
+use hi_crypto::jubjub::{edwards, FixedGenerators, JubjubEngine, JubjubParams, Unknown};
+use hi_crypto::jubjub::edwards::Point;
+
+// 29-level note commitment tree: the path holds one uncle per level, and
+// the root reached after the last level is the public anchor.
+pub const TREE_DEPTH: usize = 29;
+
+pub struct Spend<'a, E: JubjubEngine> {
+    pub params: &'a E::Params,
+
+    // value, in zatoshi
+    pub value: Option<u64>,
+    pub value_randomness: Option<E::Fs>,
+
+    // spend authorizing key; only the re-randomized rk is revealed
+    pub ak: Option<edwards::Point<E, Unknown>>,
+    // rk = ak + [alpha]*SpendingKeyGenerator
+    pub alpha: Option<E::Fs>,
+    pub nullifier_key: Option<edwards::Point<E, Unknown>>,
+
+    // diversified base and transmission key of the note's address
+    pub g_d: Option<edwards::Point<E, Unknown>>,
+    pub pk_d: Option<edwards::Point<E, Unknown>>,
+    pub commitment_randomness: Option<E::Fs>,
+
+    // one (uncle, is_right_sibling) per level; the is_right_sibling bits
+    // are this note's position, and feed into the nullifier below -- no
+    // separate position witness, so the path and nullifier can't desync.
+    pub auth_path: Vec<Option<(E::Fr, bool)>>,
+}
+
+impl<'a, E: JubjubEngine> Circuit<E> for Spend<'a, E> {
+    fn synthesize<CS: ConstraintSystem<E>>(
+        self,
+        cs: &mut CS,
+    ) -> Result<(), SynthesisError>
+    {
+        assert_eq!(self.auth_path.len(), TREE_DEPTH);
+
+        // Value commitment: cv = [value]*ValueCommitmentValue + [value_randomness]*ValueCommitmentRandomness
+        let value_bits = u64_into_boolean_vec_le(cs.namespace(||"value bits"), self.value)?;
+
+        let value_generator = EdwardsPoint::witness(
+            cs.namespace(||"value commitment value generator"),
+            Some(Point::from(self.params.generator(FixedGenerators::ValueCommitmentValue).clone())),
+            self.params,
+        )?;
+        let value_point = value_generator.mul(cs.namespace(||"[value] generator"), &value_bits, self.params)?;
+
+        let rcv_bits = field_into_boolean_vec_le(cs.namespace(||"value_randomness bits"), self.value_randomness)?;
+        let rcv_generator = EdwardsPoint::witness(
+            cs.namespace(||"value commitment randomness generator"),
+            Some(Point::from(self.params.generator(FixedGenerators::ValueCommitmentRandomness).clone())),
+            self.params,
+        )?;
+        let rcv_point = rcv_generator.mul(cs.namespace(||"[value_randomness] generator"), &rcv_bits, self.params)?;
+
+        let cv = value_point.add(cs.namespace(||"cv"), &rcv_point, self.params)?;
+        cv.inputize(cs.namespace(||"inputize cv"))?;
+
+        // Spend authority: rk = ak + [alpha]*SpendingKeyGenerator, shared
+        // with VerifyRedDSASignatureDemo<_, SpendAuth> via `derive_rk`.
+        let (rk, _spend_auth_generator) = crate::spend_authority_signature::derive_rk(
+            cs.namespace(||"spend authority"),
+            self.ak,
+            self.alpha,
+            FixedGenerators::SpendingKeyGenerator,
+            self.params,
+        )?;
+        rk.inputize(cs.namespace(||"inputize rk"))?;
+
+        // Note commitment: cm = pedersen_hash(g_d || pk_d || value) + [commitment_randomness]*generator
+        let g_d = EdwardsPoint::witness(cs.namespace(||"g_d"), self.g_d, self.params)?;
+        g_d.assert_not_small_order(cs.namespace(||"g_d not small order"), self.params)?;
+        let pk_d = EdwardsPoint::witness(cs.namespace(||"pk_d"), self.pk_d, self.params)?;
+
+        let mut note_contents = vec![];
+        note_contents.extend(g_d.repr(cs.namespace(||"g_d repr"))?);
+        note_contents.extend(pk_d.repr(cs.namespace(||"pk_d repr"))?);
+        note_contents.extend(value_bits.clone());
+
+        let cm = pedersen_hash(
+            cs.namespace(||"note content hash"),
+            Personalization::NoteCommitment,
+            &note_contents,
+            self.params,
+        )?;
+
+        let rcm_bits = field_into_boolean_vec_le(cs.namespace(||"commitment_randomness bits"), self.commitment_randomness)?;
+        let rcm_generator = EdwardsPoint::witness(
+            cs.namespace(||"note commitment randomness generator"),
+            Some(Point::from(self.params.generator(FixedGenerators::NoteCommitmentRandomness).clone())),
+            self.params,
+        )?;
+        let rcm_point = rcm_generator.mul(cs.namespace(||"[commitment_randomness] generator"), &rcm_bits, self.params)?;
+        let cm = cm.add(cs.namespace(||"cm"), &rcm_point, self.params)?;
+
+        // Merkle path: fold the leaf up to the anchor, which is a public
+        // input, collecting the position bits (this note's index in the
+        // tree) along the way so the nullifier below is bound to the same
+        // position the path actually proves.
+        let mut cur = cm.get_x().clone();
+        let mut position_bits = Vec::with_capacity(TREE_DEPTH);
+        for (i, layer) in self.auth_path.into_iter().enumerate() {
+            let cs = &mut cs.namespace(|| format!("merkle tree hash {}", i));
+
+            let (uncle_value, direction_value) = match layer {
+                Some((uncle, direction)) => (Some(uncle), Some(direction)),
+                None => (None, None),
+            };
+
+            let direction = Boolean::from(AllocatedBit::alloc(cs.namespace(||"direction bit"), direction_value)?);
+            let uncle = AllocatedNum::alloc(cs.namespace(||"uncle"), || {
+                uncle_value.ok_or(SynthesisError::AssignmentMissing)
+            })?;
+
+            let (xl, xr) = AllocatedNum::conditionally_reverse(
+                cs.namespace(||"conditional reversal of preimage"),
+                &cur,
+                &uncle,
+                &direction,
+            )?;
+
+            let mut preimage = vec![];
+            preimage.extend(xl.to_bits_le(cs.namespace(||"xl bits"))?);
+            preimage.extend(xr.to_bits_le(cs.namespace(||"xr bits"))?);
+
+            cur = pedersen_hash(
+                cs.namespace(||"computation of pedersen hash"),
+                Personalization::MerkleTree(i),
+                &preimage,
+                self.params,
+            )?.get_x().clone();
+
+            position_bits.push(direction);
+        }
+
+        cur.inputize(cs.namespace(||"anchor"))?;
+
+        // Nullifier: nf = BLAKE2s(nk_repr || position || cm_repr), where
+        // `position` is exactly the direction bits walked above.
+        let nullifier_key = EdwardsPoint::witness(cs.namespace(||"nullifier key"), self.nullifier_key, self.params)?;
+
+        let mut nullifier_preimage = vec![];
+        nullifier_preimage.extend(nullifier_key.repr(cs.namespace(||"nullifier key repr"))?);
+        nullifier_preimage.extend(position_bits);
+        nullifier_preimage.extend(cm.repr(cs.namespace(||"cm repr"))?);
+
+        let nullifier = blake2s(cs.namespace(||"nullifier hash"), &nullifier_preimage, b"Zcash_nf")?;
+        Boolean::inputize_vec(cs.namespace(||"inputize nullifier"), &nullifier)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hi_crypto::bellman::pairing::bls12_381::Bls12;
+    use hi_crypto::circuit::test::TestConstraintSystem;
+    use hi_crypto::jubjub::JubjubBls12;
+    use hi_crypto::redjubjub::{PrivateKey, PublicKey};
+    use rand::{thread_rng, Rng};
+
+    fn random_point(
+        p_g: FixedGenerators,
+        params: &<Bls12 as JubjubEngine>::Params,
+        rng: &mut impl Rng,
+    ) -> edwards::Point<Bls12, Unknown> {
+        // A public key for a random private key is a convenient source of
+        // valid (non-small-order) Jubjub points.
+        PublicKey::from_private(&PrivateKey::<Bls12>(rng.gen()), p_g, params).0
+    }
+
+    #[test]
+    fn spend_circuit_is_satisfied_with_a_valid_witness_and_rejects_a_small_order_g_d() {
+        let params = &JubjubBls12::new();
+        let rng = &mut thread_rng();
+        let p_g = FixedGenerators::SpendingKeyGenerator;
+
+        let auth_path: Vec<_> = (0..TREE_DEPTH).map(|_| Some((rng.gen(), rng.gen()))).collect();
+
+        let instance = Spend::<Bls12> {
+            params,
+            value: Some(42),
+            value_randomness: Some(rng.gen()),
+            ak: Some(random_point(p_g, params, rng)),
+            alpha: Some(rng.gen()),
+            nullifier_key: Some(random_point(p_g, params, rng)),
+            g_d: Some(random_point(p_g, params, rng)),
+            pk_d: Some(random_point(p_g, params, rng)),
+            commitment_randomness: Some(rng.gen()),
+            auth_path: auth_path.clone(),
+        };
+
+        let mut cs = TestConstraintSystem::<Bls12>::new();
+        instance.synthesize(&mut cs).unwrap();
+
+        assert!(cs.is_satisfied());
+
+        // A note whose diversified base is of small order must be rejected
+        // by `assert_not_small_order`, the same guard `derive_rk` already
+        // runs on `ak`.
+        let tampered = Spend::<Bls12> {
+            params,
+            value: Some(42),
+            value_randomness: Some(rng.gen()),
+            ak: Some(random_point(p_g, params, rng)),
+            alpha: Some(rng.gen()),
+            nullifier_key: Some(random_point(p_g, params, rng)),
+            g_d: Some(edwards::Point::<Bls12, Unknown>::zero()),
+            pk_d: Some(random_point(p_g, params, rng)),
+            commitment_randomness: Some(rng.gen()),
+            auth_path,
+        };
+
+        // The check allocates the inverse of x([8]g_d), which is zero here,
+        // so synthesis itself fails with DivisionByZero.
+        let mut cs = TestConstraintSystem::<Bls12>::new();
+        assert!(tampered.synthesize(&mut cs).is_err());
+    }
+}
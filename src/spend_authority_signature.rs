@@ -22,7 +22,7 @@ use hi_crypto::circuit::blake2b::blake2b;
 use hi_crypto::circuit::boolean::{Boolean, field_into_boolean_vec_le, u8_vec_into_boolean_vec_le};
 use hi_crypto::circuit::ecc::EdwardsPoint;
 use hi_crypto::circuit::multipack::pack_into_inputs;
-use hi_crypto::jubjub::{edwards, JubjubEngine, Unknown, JubjubParams};
+use hi_crypto::jubjub::{edwards, FixedGenerators, JubjubEngine, Unknown, JubjubParams};
 use hi_crypto::jubjub::{JubjubBls12, fs::Fs};
 use hi_crypto::redjubjub::{
     PrivateKey, PublicKey
@@ -30,6 +30,49 @@ use hi_crypto::redjubjub::{
 // For randomness (during paramgen and proof generation)
 use rand::{Rng, thread_rng};
 use hi_crypto::jubjub::edwards::Point;
+use std::marker::PhantomData;
+
+/// Distinguishes the two RedDSA parameterizations used by Sapling: they
+/// share every wire in the circuit below, and each one supplies the fixed
+/// generator the key is re-randomized/signed against and the 16-byte
+/// string that personalizes the `h_star` blake2b hash. Sapling happens to use
+/// the same personalization for both `SpendAuth` and `Binding`, so don't
+/// "fix" one to a different value -- the two only ever diverge on `generator()`.
+trait SigType {
+    fn generator() -> FixedGenerators;
+    fn personalization() -> &'static [u8; 16];
+}
+
+/// The spend authorization signature: re-randomizes `ak` against the
+/// `SpendingKeyGenerator`.
+#[derive(Clone)]
+struct SpendAuth;
+
+impl SigType for SpendAuth {
+    fn generator() -> FixedGenerators {
+        FixedGenerators::SpendingKeyGenerator
+    }
+
+    fn personalization() -> &'static [u8; 16] {
+        b"Zcash_RedJubjubH"
+    }
+}
+
+/// The binding signature: re-randomizes against the
+/// `ValueCommitmentRandomness` generator, binding the proof to the sum of
+/// value commitment randomness across a transaction.
+#[derive(Clone)]
+struct Binding;
+
+impl SigType for Binding {
+    fn generator() -> FixedGenerators {
+        FixedGenerators::ValueCommitmentRandomness
+    }
+
+    fn personalization() -> &'static [u8; 16] {
+        b"Zcash_RedJubjubH"
+    }
+}
 
 #[derive(Clone)]
 struct SpendAuthoritySignature<E: JubjubEngine> {
@@ -37,24 +80,66 @@ struct SpendAuthoritySignature<E: JubjubEngine> {
     s: Option<E::Fs>,
 }
 
+/// Witnesses `ak` and `alpha` and derives the re-randomized key
+/// `rk = ak + [alpha]*generator`, where `generator` is the fixed generator
+/// named by `generator_kind`. Shared by `VerifyRedDSASignatureDemo` and
+/// `spend::Spend`, which both need this same re-randomization step, so the
+/// two copies of the constraint logic can't drift apart.
+///
+/// Returns `(rk, generator)`; callers that also need the (possibly
+/// negated) generator for their own equations, as the signature check
+/// below does, get it back instead of re-witnessing it.
+pub(crate) fn derive_rk<E: JubjubEngine, CS: ConstraintSystem<E>>(
+    mut cs: CS,
+    ak: Option<edwards::Point<E, Unknown>>,
+    alpha: Option<E::Fs>,
+    generator_kind: FixedGenerators,
+    params: &E::Params,
+) -> Result<(EdwardsPoint<E>, EdwardsPoint<E>), SynthesisError> {
+    let ak = EdwardsPoint::witness(cs.namespace(||"ak"), ak, params)?;
+    ak.assert_not_small_order(cs.namespace(||"ak not small order"), params)?;
+
+    let generator_point = Point::<E, Unknown>::from(params.generator(generator_kind).clone());
+    let generator = EdwardsPoint::witness(cs.namespace(||"spend auth generator witness"), Some(generator_point), params)?;
+
+    let alpha_bits = field_into_boolean_vec_le(cs.namespace(||"alpha into bits"), alpha)?;
+    let alpha_g = generator.mul(cs.namespace(||"[alpha] spend auth generator"), &alpha_bits, params)?;
+
+    // rk = ak + [alpha]*generator, the re-randomized key that actually
+    // gets bound into the caller's own equation.
+    let rk = ak.add(cs.namespace(||"rk = ak + [alpha] generator"), &alpha_g, params)?;
+
+    Ok((rk, generator))
+}
 
 #[derive(Clone)]
-struct VerifySpendAuthoritySignatureDemo<'a, E: JubjubEngine> {
+struct VerifyRedDSASignatureDemo<'a, E: JubjubEngine, T: SigType> {
     msg_hash: Option<Vec<u8>>,
     signature: SpendAuthoritySignature<E>,
-    public_key: Option<edwards::Point<E, Unknown>>,
-    generator: Option<edwards::Point<E, Unknown>>,
+    // The spend authorizing key `ak`, kept private: only the re-randomized
+    // `rk` derived from it below is ever exposed to the verifier.
+    ak: Option<edwards::Point<E, Unknown>>,
+    // The randomizer `alpha` used to derive `rk = ak + [alpha]*generator`,
+    // fresh for every spend so that `rk` can't be linked back to `ak`.
+    alpha: Option<E::Fs>,
     params: &'a E::Params,
+    _sig_type: PhantomData<T>,
 }
 
-impl<'a, E: JubjubEngine> Circuit<E> for VerifySpendAuthoritySignatureDemo<'a, E> {
+impl<'a, E: JubjubEngine, T: SigType> Circuit<E> for VerifyRedDSASignatureDemo<'a, E, T> {
     fn synthesize<CS: ConstraintSystem<E>>(
         self,
         cs: &mut CS,
     ) -> Result<(), SynthesisError>
     {
-        let vk = EdwardsPoint::witness(cs.namespace(||"vk"), self.public_key, self.params)?;
-        vk.assert_not_small_order(cs.namespace(||"vk not small order"), &self.params)?;
+        let (rk, spend_auth_generator) = derive_rk(
+            cs.namespace(||"spend authority"),
+            self.ak,
+            self.alpha,
+            T::generator(),
+            self.params,
+        )?;
+        rk.inputize(cs.namespace(||"inputize rk"))?;
 
         let r = EdwardsPoint::witness(cs.namespace(||"r"), self.signature.r, self.params)?;
         r.inputize(cs.namespace(||"inputize signature.r"))?;
@@ -65,9 +150,9 @@ impl<'a, E: JubjubEngine> Circuit<E> for VerifySpendAuthoritySignatureDemo<'a, E
         input_bits.extend(r_bar_bit.into_iter());
         input_bits.resize(256, Boolean::Constant(false));
 
-        // //add vk to hash
-        let vk_bits = vk.repr(cs.namespace(||"vk unpack to bits"))?;
-        input_bits.extend(vk_bits.into_iter());
+        // //add rk to hash
+        let rk_bits = rk.repr(cs.namespace(||"rk unpack to bits"))?;
+        input_bits.extend(rk_bits.into_iter());
         input_bits.resize(512, Boolean::Constant(false));
 
         //add msg hash to hash
@@ -75,16 +160,15 @@ impl<'a, E: JubjubEngine> Circuit<E> for VerifySpendAuthoritySignatureDemo<'a, E
         input_bits.extend(msg_hash_bits.into_iter());
         input_bits.resize(768, Boolean::Constant(false));
 
-        let h_star = blake2b(cs.namespace(||"blake2b hash"), &input_bits, b"Zcash_RedJubjubH").unwrap();
+        let h_star = blake2b(cs.namespace(||"blake2b hash"), &input_bits, T::personalization()).unwrap();
         assert_eq!(h_star.len(), 512);
 
         let s_bit = field_into_boolean_vec_le(cs.namespace(||"scalar into bits"), self.signature.s)?;
         pack_into_inputs(cs.namespace(||"signature.s inputize"), &s_bit)?;
 
-        let generator = EdwardsPoint::witness(cs.namespace(||"generator witness"), self.generator, self.params)?;
-        let generator = generator.negate(cs.namespace(||"generator negate"), self.params)?;
+        let generator = spend_auth_generator.negate(cs.namespace(||"generator negate"), self.params)?;
 
-        let mut sig = vk.mul(cs.namespace(||"hstar * vk"), &h_star, self.params)?;
+        let mut sig = rk.mul(cs.namespace(||"hstar * rk"), &h_star, self.params)?;
         let tmp = generator.mul(cs.namespace(||"-s * generator"), &s_bit, self.params)?;
         sig = sig.add(cs.namespace(||"signature add1"), &tmp, self.params)?;
         sig = sig.add(cs.namespace(||"signature add2"), &r, self.params)?;
@@ -113,20 +197,22 @@ impl<'a, E: JubjubEngine> Circuit<E> for VerifySpendAuthoritySignatureDemo<'a, E
 
 #[test]
 fn test_spend_auth_sig_bls12() {
-    use hi_crypto::jubjub::FixedGenerators;
-
     let jubjubbls12_params = &JubjubBls12::new();
     // This may not be cryptographically safe, use
     // `OsRng` (for example) in production software.
     let rng = &mut thread_rng();
-    let p_g = FixedGenerators::SpendingKeyGenerator;
-    let gen = Point::<Bls12, Unknown>::from(jubjubbls12_params.generator(p_g).clone());
-
-    let sk = PrivateKey::<Bls12>(rng.gen());
-    let vk = PublicKey::from_private(&sk, p_g, jubjubbls12_params);
+    let p_g = SpendAuth::generator();
+
+    // `ak`, the long-lived spend authorizing key, is never revealed; every
+    // spend instead re-randomizes it with a fresh `alpha`.
+    let ak = PrivateKey::<Bls12>(rng.gen());
+    let vk = PublicKey::from_private(&ak, p_g, jubjubbls12_params);
+    let alpha = rng.gen();
+    let rsk = ak.randomize(alpha);
+    let rk = vk.randomize(alpha, p_g, jubjubbls12_params);
     let msg = b"This is a test message for sign.";
     let mut data_to_be_signed = [0u8; 64];
-    vk.write(&mut data_to_be_signed[0..32]).unwrap();
+    rk.write(&mut data_to_be_signed[0..32]).unwrap();
     (&mut data_to_be_signed[32..64]).copy_from_slice(&({ &*msg })[..]);
 
     println!("Creating parameters...");
@@ -136,12 +222,13 @@ fn test_spend_auth_sig_bls12() {
     };
     // Create parameters for our circuit
     let params = {
-        let c = VerifySpendAuthoritySignatureDemo::<Bls12> {
+        let c = VerifyRedDSASignatureDemo::<Bls12, SpendAuth> {
             msg_hash: None,
             signature: signature,
-            public_key: None,
-            generator: None,
-            params: jubjubbls12_params
+            ak: None,
+            alpha: None,
+            params: jubjubbls12_params,
+            _sig_type: PhantomData,
         };
 
         generate_random_parameters(c, rng).unwrap()
@@ -162,9 +249,9 @@ fn test_spend_auth_sig_bls12() {
     for cnt in 0..SAMPLES {
         proof_vec.truncate(0);
 
-        //generate signature
-        let sig = sk.sign(&data_to_be_signed, rng, p_g, jubjubbls12_params);
-        assert!(vk.verify(&data_to_be_signed, &sig, p_g, jubjubbls12_params));
+        //generate signature with the re-randomized key, matching rk
+        let sig = rsk.sign(&data_to_be_signed, rng, p_g, jubjubbls12_params);
+        assert!(rk.verify(&data_to_be_signed, &sig, p_g, jubjubbls12_params));
 
         let mut sig_bytes = [0u8; 64];
         sig.write(&mut sig_bytes[..]).unwrap();
@@ -176,15 +263,16 @@ fn test_spend_auth_sig_bls12() {
         {
             // Create an instance of our circuit (with the
             // witness)
-            let c = VerifySpendAuthoritySignatureDemo::<Bls12> {
+            let c = VerifyRedDSASignatureDemo::<Bls12, SpendAuth> {
                 msg_hash: Some(msg.iter().cloned().collect()),
                 signature: SpendAuthoritySignature::<Bls12> {
                     r: Some(r_point.clone()),
                     s: Some(s_fs),
                 },
-                public_key: Some(vk.clone().0),
-                generator: Some(gen.clone()),
-                params: jubjubbls12_params
+                ak: Some(vk.clone().0),
+                alpha: Some(alpha),
+                params: jubjubbls12_params,
+                _sig_type: PhantomData,
             };
 
             // Create a groth16 proof with our parameters.
@@ -197,6 +285,10 @@ fn test_spend_auth_sig_bls12() {
 
         //generate public inputs
         let mut image = vec![];
+        let rk_x_y = rk.0.into_xy();
+        image.push(rk_x_y.0);
+        image.push(rk_x_y.1);
+
         let r_point_x_y = r_point.into_xy();
         image.push(r_point_x_y.0);
         image.push(r_point_x_y.1);
@@ -228,3 +320,74 @@ fn test_spend_auth_sig_bls12() {
     println!("Average verifying time: {:?} seconds", verifying_avg);
 }
 
+#[test]
+fn test_binding_sig_bls12() {
+    let jubjubbls12_params = &JubjubBls12::new();
+    let rng = &mut thread_rng();
+    let p_g = Binding::generator();
+
+    // The binding key plays the same role `ak` does for a spend-auth
+    // signature, but it's re-randomized against `ValueCommitmentRandomness`
+    // instead of `SpendingKeyGenerator`.
+    let bk = PrivateKey::<Bls12>(rng.gen());
+    let vk = PublicKey::from_private(&bk, p_g, jubjubbls12_params);
+    let alpha = rng.gen();
+    let rsk = bk.randomize(alpha);
+    let rk = vk.randomize(alpha, p_g, jubjubbls12_params);
+    let msg = b"This is a test message for the binding signature.";
+    let mut data_to_be_signed = [0u8; 64];
+    rk.write(&mut data_to_be_signed[0..32]).unwrap();
+    (&mut data_to_be_signed[32..64]).copy_from_slice(&({ &*msg })[..]);
+
+    let signature = SpendAuthoritySignature::<Bls12> { r: None, s: None };
+    let params = {
+        let c = VerifyRedDSASignatureDemo::<Bls12, Binding> {
+            msg_hash: None,
+            signature,
+            ak: None,
+            alpha: None,
+            params: jubjubbls12_params,
+            _sig_type: PhantomData,
+        };
+
+        generate_random_parameters(c, rng).unwrap()
+    };
+    let pvk = prepare_verifying_key(&params.vk);
+
+    let sig = rsk.sign(&data_to_be_signed, rng, p_g, jubjubbls12_params);
+    assert!(rk.verify(&data_to_be_signed, &sig, p_g, jubjubbls12_params));
+
+    let mut sig_bytes = [0u8; 64];
+    sig.write(&mut sig_bytes[..]).unwrap();
+    let r_point = PublicKey::<Bls12>::read(&sig_bytes[..32], &jubjubbls12_params).unwrap().0;
+    let s_fs = PrivateKey::<Bls12>::read(&sig_bytes[32..]).unwrap().0;
+
+    let c = VerifyRedDSASignatureDemo::<Bls12, Binding> {
+        msg_hash: Some(msg.iter().cloned().collect()),
+        signature: SpendAuthoritySignature::<Bls12> {
+            r: Some(r_point.clone()),
+            s: Some(s_fs),
+        },
+        ak: Some(vk.clone().0),
+        alpha: Some(alpha),
+        params: jubjubbls12_params,
+        _sig_type: PhantomData,
+    };
+    let proof = create_random_proof(c, &params, rng).unwrap();
+
+    let mut image = vec![];
+    let rk_x_y = rk.0.into_xy();
+    image.push(rk_x_y.0);
+    image.push(rk_x_y.1);
+
+    let r_point_x_y = r_point.into_xy();
+    image.push(r_point_x_y.0);
+    image.push(r_point_x_y.1);
+
+    let s_hex = to_hex::<Fs>(&s_fs);
+    let s_fr = from_hex::<Fr>(&s_hex).unwrap();
+    image.push(s_fr);
+
+    assert!(verify_proof(&pvk, &proof, &image).unwrap());
+}
+